@@ -1,24 +1,289 @@
+use std::fmt;
 use std::fs::{self, File};
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use anyhow::{Context, Result};
-use chrono::Utc;
-use clap::Parser;
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+use tracing::{error, info, info_span, warn};
 use uuid::Uuid;
 
 const MEMORY_ROOT: &str = ".claude/memory_anchors";
 const ANCHORS_FILE: &str = "anchors.json";
 
+/// Number of lines of context hashed on either side of an anchor.
+const CONTEXT_RADIUS: usize = 3;
+
+/// Minimum score a relocation candidate needs to be accepted.
+const RELOCATE_THRESHOLD: u32 = 1;
+
+/// Minimum lead the best candidate must have over the runner-up before we
+/// trust it enough to auto-relocate; otherwise the anchor is left ambiguous.
+const RELOCATE_MARGIN: u32 = 2;
+
+/// Weight given to a match on the anchored line itself vs. a context line.
+const CENTER_WEIGHT: u32 = 3;
+
+/// Directories skipped while walking the repository for `reindex`.
+const IGNORED_DIRS: &[&str] = &[".git", "target", "node_modules", ".svn", ".hg"];
+
+const ANCHOR_MARKER: &str = "CLAUDE_ANCHOR[key=";
+
+/// FNV-1a constants. Fingerprints built from these are persisted to
+/// anchors.json and compared against freshly computed ones on a later run,
+/// so the algorithm has to be one we control — `std`'s `DefaultHasher` makes
+/// no stability guarantee across Rust releases, and a toolchain upgrade
+/// could otherwise mass-invalidate every stored fingerprint at once.
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Errors surfaced from anchor operations, instead of the ad-hoc `println!`s
+/// this tool used to fall back on.
+#[derive(Debug, Error)]
+enum AnchorError {
+    #[error("file not found: {0}")]
+    FileNotFound(PathBuf),
+    #[error("invalid line {line} for file with {max} lines")]
+    InvalidLine { line: usize, max: usize },
+    #[error("anchors store at {0} is corrupt")]
+    CorruptStore(PathBuf),
+    #[error("failed to write {0}")]
+    WriteFailed(PathBuf),
+    #[error("unknown anchor status \"{0}\"")]
+    InvalidStatus(String),
+}
+
+/// Lifecycle state of a tracked anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AnchorStatus {
+    Active,
+    Resolved,
+    Orphaned,
+    Moved,
+    Ambiguous,
+}
+
+impl AnchorStatus {
+    const fn as_str(self) -> &'static str {
+        match self {
+            AnchorStatus::Active => "active",
+            AnchorStatus::Resolved => "resolved",
+            AnchorStatus::Orphaned => "orphaned",
+            AnchorStatus::Moved => "moved",
+            AnchorStatus::Ambiguous => "ambiguous",
+        }
+    }
+}
+
+impl fmt::Display for AnchorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AnchorStatus {
+    type Err = AnchorError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(AnchorStatus::Active),
+            "resolved" => Ok(AnchorStatus::Resolved),
+            "orphaned" => Ok(AnchorStatus::Orphaned),
+            "moved" => Ok(AnchorStatus::Moved),
+            "ambiguous" => Ok(AnchorStatus::Ambiguous),
+            other => Err(AnchorError::InvalidStatus(other.to_string())),
+        }
+    }
+}
+
+impl Serialize for AnchorStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for AnchorStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+/// What an anchor marks: a single line, a block, a function, a TODO, or an
+/// arbitrary caller-supplied tag we don't have a dedicated variant for.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+enum AnchorKind {
+    #[default]
+    Line,
+    Block,
+    Function,
+    Todo,
+    Other(String),
+}
+
+impl AnchorKind {
+    fn as_str(&self) -> &str {
+        match self {
+            AnchorKind::Line => "line",
+            AnchorKind::Block => "block",
+            AnchorKind::Function => "function",
+            AnchorKind::Todo => "todo",
+            AnchorKind::Other(s) => s,
+        }
+    }
+}
+
+impl From<&str> for AnchorKind {
+    fn from(s: &str) -> Self {
+        match s {
+            "line" => AnchorKind::Line,
+            "block" => AnchorKind::Block,
+            "function" => AnchorKind::Function,
+            "todo" => AnchorKind::Todo,
+            other => AnchorKind::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for AnchorKind {
+    fn from(s: String) -> Self {
+        AnchorKind::from(s.as_str())
+    }
+}
+
+impl fmt::Display for AnchorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for AnchorKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for AnchorKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(AnchorKind::from(s))
+    }
+}
+
 #[derive(Parser, Debug)]
-#[command(version, about = "Drop a CLAUDE/AGENTS memory anchor for KB synch", long_about = None)]
-struct Args {
+#[command(version, about = "Manage CLAUDE/AGENTS memory anchors for KB synch", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Drop a new anchor at file:line.
+    Drop {
+        file: PathBuf,
+        #[arg(value_parser = clap::value_parser!(usize))]
+        line: usize,
+        desc: String,
+        kind: Option<String>,
+        /// Comma-separated tags to attach to the anchor.
+        #[arg(long, value_delimiter = ',')]
+        tags: Vec<String>,
+    },
+    /// List tracked anchors, optionally filtered.
+    List {
+        #[arg(long)]
+        status: Option<String>,
+        #[arg(long)]
+        path: Option<String>,
+        #[arg(long)]
+        kind: Option<String>,
+    },
+    /// Show the full record for a single anchor.
+    Show { key: String },
+    /// Mark an anchor resolved.
+    Resolve {
+        key: String,
+        /// Also remove the inline CLAUDE_ANCHOR comment from the file.
+        #[arg(long)]
+        strip: bool,
+    },
+    /// Delete an anchor entry and its inline comment.
+    Remove { key: String },
+    /// Edit an existing anchor's fields without recreating it.
+    Amend {
+        key: String,
+        #[arg(long)]
+        desc: Option<String>,
+        #[arg(long)]
+        line: Option<usize>,
+        #[arg(long)]
+        kind: Option<String>,
+        /// Replace the anchor's tag list (comma-separated).
+        #[arg(long, value_delimiter = ',')]
+        tags: Option<Vec<String>>,
+    },
+    /// Re-check anchors against on-disk content and relocate drifted ones.
+    Resync {
+        /// Resync only this anchor; omit to resync every tracked anchor.
+        key: Option<String>,
+    },
+    /// Walk the repo for CLAUDE_ANCHOR markers and reconcile them with anchors.json.
+    Reindex {
+        /// Root directory to scan.
+        #[arg(long, default_value = ".")]
+        root: PathBuf,
+    },
+    /// Query anchors by tag/status/path/date and render a report.
+    Export {
+        #[arg(long)]
+        tag: Option<String>,
+        #[arg(long)]
+        status: Option<String>,
+        #[arg(long = "path-prefix")]
+        path_prefix: Option<String>,
+        /// Only include anchors created on or after this date (YYYY-MM-DD).
+        #[arg(long)]
+        since: Option<String>,
+        /// Only include anchors created on or before this date (YYYY-MM-DD).
+        #[arg(long)]
+        until: Option<String>,
+        #[arg(long, value_enum, default_value = "table")]
+        format: ExportFormat,
+    },
+    /// Drop every anchor listed in a JSON or TOML spec file, transactionally.
+    Batch {
+        /// Path to a spec file listing `{file, line, desc, kind, tags}` records.
+        spec: PathBuf,
+    },
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum ExportFormat {
+    Table,
+    Json,
+    Markdown,
+}
+
+#[derive(Deserialize, Debug)]
+struct BatchSpec {
+    anchors: Vec<BatchSpecEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BatchSpecEntry {
     file: PathBuf,
-    #[arg(value_parser = clap::value_parser!(usize))]
     line: usize,
     desc: String,
+    #[serde(default)]
     kind: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -47,88 +312,916 @@ struct AnchorEntry {
     key: String,
     path: String,
     line: usize,
-    kind: String,
+    kind: AnchorKind,
     description: String,
-    status: String,
+    status: AnchorStatus,
     created: String,
+    /// Free-form tags for querying/export, e.g. `["review", "perf"]`.
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Hash of the line the anchor points at, captured at drop time.
+    anchor_hash: String,
+    /// Hashes of up to `CONTEXT_RADIUS` lines before the anchored line.
+    context_before: Vec<String>,
+    /// Hashes of up to `CONTEXT_RADIUS` lines after the anchored line.
+    context_after: Vec<String>,
+    /// Hash of the whole context window concatenated together.
+    window_hash: String,
 }
 
 fn main() {
+    tracing_subscriber::fmt::init();
+
     if let Err(err) = run() {
-        eprintln!("Error: {err}");
+        error!("{err}");
         std::process::exit(1);
     }
 }
 
 fn run() -> Result<()> {
-    let args = Args::parse();
+    let cli = Cli::parse();
 
-    let file_path = args.file;
-    let line_num = args.line;
-    let description = args.desc;
-    let kind = args.kind.unwrap_or_else(|| "line".to_string());
+    let anchor_root = PathBuf::from(MEMORY_ROOT);
+    fs::create_dir_all(&anchor_root)
+        .with_context(|| format!("Failed to create {}", anchor_root.display()))?;
+    let anchors_path = anchor_root.join(ANCHORS_FILE);
 
-    if !file_path.exists() {
-        println!("File {} not found.", file_path.display());
-        return Ok(());
+    match cli.command {
+        Command::Drop {
+            file,
+            line,
+            desc,
+            kind,
+            tags,
+        } => cmd_drop(&anchors_path, file, line, desc, kind, tags),
+        Command::List { status, path, kind } => cmd_list(&anchors_path, status, path, kind),
+        Command::Show { key } => cmd_show(&anchors_path, &key),
+        Command::Resolve { key, strip } => cmd_resolve(&anchors_path, &key, strip),
+        Command::Remove { key } => cmd_remove(&anchors_path, &key),
+        Command::Amend {
+            key,
+            desc,
+            line,
+            kind,
+            tags,
+        } => cmd_amend(&anchors_path, &key, desc, line, kind, tags),
+        Command::Resync { key } => cmd_resync(&anchors_path, key.as_deref()),
+        Command::Reindex { root } => cmd_reindex(&anchors_path, &root),
+        Command::Export {
+            tag,
+            status,
+            path_prefix,
+            since,
+            until,
+            format,
+        } => cmd_export(&anchors_path, tag, status, path_prefix, since, until, format),
+        Command::Batch { spec } => cmd_batch(&anchors_path, &spec),
     }
+}
 
-    let mut lines = read_file_lines(&file_path)?;
-    if line_num == 0 {
-        println!(
-            "Invalid line {} for file with {} lines.",
-            line_num,
-            lines.len()
-        );
-        return Ok(());
+fn cmd_drop(
+    anchors_path: &Path,
+    file_path: PathBuf,
+    line_num: usize,
+    description: String,
+    kind: Option<String>,
+    tags: Vec<String>,
+) -> Result<()> {
+    let kind = kind.map(AnchorKind::from).unwrap_or_default();
+
+    if !file_path.exists() {
+        return Err(AnchorError::FileNotFound(file_path).into());
     }
 
-    let max_valid = lines.len() + 1;
-    if line_num > max_valid {
-        println!(
-            "Invalid line {} for file with {} lines.",
-            line_num,
-            lines.len()
-        );
-        return Ok(());
+    let lines = read_file_lines(&file_path)?;
+    if line_num == 0 || line_num > lines.len() + 1 {
+        return Err(AnchorError::InvalidLine {
+            line: line_num,
+            max: lines.len(),
+        }
+        .into());
     }
 
+    let path_str = file_path.display().to_string();
+    let mut record = load_record(anchors_path)?;
+
     let key = generate_key();
     let comment = build_comment(&file_path, &key, &description);
     let insert_at = line_num - 1;
-    lines.insert(insert_at, comment);
-    fs::write(&file_path, lines.concat())
-        .with_context(|| format!("Failed to write updated content to {}", file_path.display()))?;
+    let mut new_lines = lines;
+    new_lines.insert(insert_at, comment);
+    atomic_write(&file_path, &new_lines.concat())?;
 
-    let anchor_root = PathBuf::from(MEMORY_ROOT);
-    fs::create_dir_all(&anchor_root)
-        .with_context(|| format!("Failed to create {}", anchor_root.display()))?;
+    // `line` records where the anchor's own marker comment lives, so the
+    // fingerprint must be taken post-insertion, centered on that comment —
+    // not on the content line it displaced.
+    let window = context_window(&new_lines, insert_at, CONTEXT_RADIUS);
+
+    // Our own insertion shifts every locatable anchor at or after the
+    // insertion point down by one line.
+    for entry in record
+        .anchors
+        .iter_mut()
+        .filter(|e| e.path == path_str && e.status != AnchorStatus::Orphaned && e.line >= line_num)
+    {
+        entry.line += 1;
+    }
 
-    let anchors_path = anchor_root.join(ANCHORS_FILE);
-    let mut record = load_record(&anchors_path)?;
     let now = current_timestamp();
     record.anchors.push(AnchorEntry {
         key: key.clone(),
-        path: file_path.display().to_string(),
+        path: path_str,
         line: line_num,
         kind,
         description,
-        status: "active".to_string(),
+        status: AnchorStatus::Active,
         created: now,
+        tags,
+        anchor_hash: window.anchor_hash,
+        context_before: window.before,
+        context_after: window.after,
+        window_hash: window.window_hash,
     });
     record.touch_generated();
-    write_record(&anchors_path, &record)?;
+    write_record(anchors_path, &record)?;
+
+    info!(key = %key, file = %file_path.display(), line = line_num, "anchor dropped");
+
+    Ok(())
+}
+
+/// Lists tracked anchors. This is a query command, not a mutation, so its
+/// output — like `show`/`export` — goes straight to stdout rather than
+/// through `tracing`: it's the result the caller asked for, not an
+/// operational log of what the tool did.
+fn cmd_list(
+    anchors_path: &Path,
+    status: Option<String>,
+    path: Option<String>,
+    kind: Option<String>,
+) -> Result<()> {
+    let record = load_record(anchors_path)?;
+
+    let status = status.map(|s| s.parse::<AnchorStatus>()).transpose()?;
+    let kind = kind.map(AnchorKind::from);
+
+    let matches: Vec<&AnchorEntry> = record
+        .anchors
+        .iter()
+        .filter(|e| status.is_none_or(|s| e.status == s))
+        .filter(|e| path.as_deref().is_none_or(|p| glob_match(p, &e.path)))
+        .filter(|e| kind.as_ref().is_none_or(|k| &e.kind == k))
+        .collect();
+
+    if matches.is_empty() {
+        println!("No anchors match.");
+        return Ok(());
+    }
+
+    for entry in matches {
+        println!(
+            "{}  {}:{:<6} [{}/{}]  {}",
+            entry.key, entry.path, entry.line, entry.kind, entry.status, entry.description
+        );
+    }
+
+    Ok(())
+}
+
+fn cmd_show(anchors_path: &Path, key: &str) -> Result<()> {
+    let record = load_record(anchors_path)?;
+    match find_entry(&record, key) {
+        Some(entry) => {
+            println!("{:#?}", entry);
+            Ok(())
+        }
+        None => {
+            println!("No anchor with key {key}.");
+            Ok(())
+        }
+    }
+}
+
+fn cmd_resolve(anchors_path: &Path, key: &str, strip: bool) -> Result<()> {
+    let mut record = load_record(anchors_path)?;
+    let Some(entry) = find_entry_mut(&mut record, key) else {
+        warn!(key, "no anchor with this key");
+        return Ok(());
+    };
+    entry.status = AnchorStatus::Resolved;
+    let path_str = entry.path.clone();
+    let path = PathBuf::from(&path_str);
+
+    if strip
+        && let Some(removed_line) = remove_anchor_comment(&path, key)?
+    {
+        shift_anchors_after_removal(&mut record, &path_str, removed_line);
+    }
+
+    record.touch_generated();
+    write_record(anchors_path, &record)?;
+    info!(key, "anchor resolved");
+    Ok(())
+}
+
+fn cmd_remove(anchors_path: &Path, key: &str) -> Result<()> {
+    let mut record = load_record(anchors_path)?;
+    let Some(pos) = record.anchors.iter().position(|e| e.key == key) else {
+        warn!(key, "no anchor with this key");
+        return Ok(());
+    };
+    let entry = record.anchors.remove(pos);
+    let path = PathBuf::from(&entry.path);
+
+    if let Some(removed_line) = remove_anchor_comment(&path, key)? {
+        shift_anchors_after_removal(&mut record, &entry.path, removed_line);
+    }
+
+    record.touch_generated();
+    write_record(anchors_path, &record)?;
+    info!(key, "anchor removed");
+    Ok(())
+}
+
+/// After deleting the marker comment at `removed_line` in `path`, every other
+/// tracked anchor further down the same file has shifted up by one line.
+fn shift_anchors_after_removal(record: &mut AnchorsRecord, path: &str, removed_line: usize) {
+    for entry in record.anchors.iter_mut().filter(|e| {
+        e.path == path && e.status != AnchorStatus::Orphaned && e.line > removed_line
+    }) {
+        entry.line -= 1;
+    }
+}
+
+fn cmd_amend(
+    anchors_path: &Path,
+    key: &str,
+    desc: Option<String>,
+    line: Option<usize>,
+    kind: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<()> {
+    let mut record = load_record(anchors_path)?;
+    let Some(entry) = find_entry_mut(&mut record, key) else {
+        warn!(key, "no anchor with this key");
+        return Ok(());
+    };
+
+    if let Some(desc) = desc {
+        entry.description = desc;
+    }
+    if let Some(kind) = kind {
+        entry.kind = AnchorKind::from(kind);
+    }
+    if let Some(tags) = tags {
+        entry.tags = tags;
+    }
+    if let Some(line) = line {
+        let path = PathBuf::from(entry.path.clone());
+        let lines = read_file_lines(&path)?;
+        if line == 0 || line > lines.len() + 1 {
+            return Err(AnchorError::InvalidLine {
+                line,
+                max: lines.len(),
+            }
+            .into());
+        }
+        let window = context_window(&lines, line - 1, CONTEXT_RADIUS);
+        entry.line = line;
+        entry.anchor_hash = window.anchor_hash;
+        entry.context_before = window.before;
+        entry.context_after = window.after;
+        entry.window_hash = window.window_hash;
+        entry.status = AnchorStatus::Active;
+    }
+
+    record.touch_generated();
+    write_record(anchors_path, &record)?;
+    info!(key, "anchor amended");
+    Ok(())
+}
+
+fn cmd_resync(anchors_path: &Path, key: Option<&str>) -> Result<()> {
+    let mut record = load_record(anchors_path)?;
+
+    let paths: Vec<String> = match key {
+        Some(key) => match find_entry(&record, key) {
+            Some(entry) => vec![entry.path.clone()],
+            None => {
+                warn!(key, "no anchor with this key");
+                return Ok(());
+            }
+        },
+        None => {
+            let mut paths: Vec<String> = record.anchors.iter().map(|e| e.path.clone()).collect();
+            paths.sort();
+            paths.dedup();
+            paths
+        }
+    };
+
+    for path in paths {
+        let lines = match read_file_lines(Path::new(&path)) {
+            Ok(lines) => lines,
+            Err(_) => {
+                for entry in record
+                    .anchors
+                    .iter_mut()
+                    .filter(|e| e.path == path && e.status != AnchorStatus::Resolved)
+                {
+                    entry.status = AnchorStatus::Orphaned;
+                }
+                continue;
+            }
+        };
+        relocate_anchors_for_path(&mut record, &path, &lines);
+    }
+
+    record.touch_generated();
+    write_record(anchors_path, &record)?;
+    info!("resync complete");
+    Ok(())
+}
+
+/// Walks `root`, greps every file for `CLAUDE_ANCHOR[key=...]` markers, and
+/// reconciles them against `anchors.json`: markers with no matching entry are
+/// imported, entries whose marker moved get corrected, and entries whose
+/// marker has vanished are flagged `"orphaned"`. Entries recorded against a
+/// path outside `root` weren't scanned and are left untouched.
+fn cmd_reindex(anchors_path: &Path, root: &Path) -> Result<()> {
+    let mut record = load_record(anchors_path)?;
+
+    let mut found: Vec<(String, String, usize, String)> = Vec::new(); // key, path, line, desc
+    for rel_path in collect_source_files(root) {
+        let disk_path = root.join(&rel_path);
+        let Ok(content) = fs::read_to_string(&disk_path) else {
+            continue;
+        };
+        let stored_path = if root == Path::new(".") {
+            rel_path.to_string_lossy().to_string()
+        } else {
+            root.join(&rel_path).to_string_lossy().to_string()
+        };
+
+        for (idx, line) in content.lines().enumerate() {
+            if let Some((key, desc)) = parse_marker(line) {
+                found.push((key, stored_path.clone(), idx + 1, desc));
+            }
+        }
+    }
+
+    let mut added = 0u32;
+    let mut updated = 0u32;
+    let mut orphaned = 0u32;
+
+    for (key, path, line, desc) in &found {
+        match find_entry_mut(&mut record, key) {
+            Some(entry) => {
+                if entry.path != *path || entry.line != *line {
+                    entry.path = path.clone();
+                    entry.line = *line;
+                    if entry.status == AnchorStatus::Orphaned {
+                        entry.status = AnchorStatus::Active;
+                    }
+                    // Refresh the fingerprint against the marker's new
+                    // position too, or a later resync would judge the
+                    // anchor against a stale window and relocate it again.
+                    let lines = read_file_lines(Path::new(path)).unwrap_or_default();
+                    let window = context_window(&lines, line.saturating_sub(1), CONTEXT_RADIUS);
+                    entry.anchor_hash = window.anchor_hash;
+                    entry.context_before = window.before;
+                    entry.context_after = window.after;
+                    entry.window_hash = window.window_hash;
+                    updated += 1;
+                }
+            }
+            None => {
+                let lines = read_file_lines(Path::new(path)).unwrap_or_default();
+                let window = context_window(&lines, line.saturating_sub(1), CONTEXT_RADIUS);
+                record.anchors.push(AnchorEntry {
+                    key: key.clone(),
+                    path: path.clone(),
+                    line: *line,
+                    kind: AnchorKind::Line,
+                    description: desc.clone(),
+                    status: AnchorStatus::Active,
+                    created: current_timestamp(),
+                    tags: Vec::new(),
+                    anchor_hash: window.anchor_hash,
+                    context_before: window.before,
+                    context_after: window.after,
+                    window_hash: window.window_hash,
+                });
+                added += 1;
+            }
+        }
+    }
+
+    let found_keys: Vec<&str> = found.iter().map(|(key, ..)| key.as_str()).collect();
+    for entry in record.anchors.iter_mut().filter(|e| {
+        path_within_root(&e.path, root)
+            && !found_keys.contains(&e.key.as_str())
+            && e.status != AnchorStatus::Orphaned
+            && e.status != AnchorStatus::Resolved
+    }) {
+        entry.status = AnchorStatus::Orphaned;
+        orphaned += 1;
+    }
+
+    record.touch_generated();
+    write_record(anchors_path, &record)?;
+
+    info!(added, updated, orphaned, "reindex complete");
+    Ok(())
+}
+
+/// Drops every anchor listed in `spec` (JSON or TOML, by extension)
+/// transactionally: every line number is validated against its file before
+/// anything is written, so one bad entry can't leave files half-anchored.
+/// Within a file, anchors are applied highest line first so earlier
+/// insertions don't shift the indices of later ones.
+fn cmd_batch(anchors_path: &Path, spec_path: &Path) -> Result<()> {
+    let content = fs::read_to_string(spec_path)
+        .with_context(|| format!("Failed to read batch spec {}", spec_path.display()))?;
+    let spec = parse_batch_spec(spec_path, &content)?;
+
+    let mut by_file: std::collections::BTreeMap<PathBuf, Vec<BatchSpecEntry>> =
+        std::collections::BTreeMap::new();
+    for entry in spec.anchors {
+        by_file.entry(entry.file.clone()).or_default().push(entry);
+    }
+
+    // Phase 1: validate every entry against its file before touching anything.
+    let mut file_lines: std::collections::BTreeMap<PathBuf, Vec<String>> =
+        std::collections::BTreeMap::new();
+    let mut had_error = false;
+
+    for (file, entries) in &by_file {
+        let span = info_span!("batch_validate", path = %file.display());
+        let _enter = span.enter();
+
+        if !file.exists() {
+            warn!(entries = entries.len(), "file not found");
+            had_error = true;
+            continue;
+        }
+
+        let lines = read_file_lines(file)?;
+        let max_valid = lines.len() + 1;
+        for entry in entries {
+            if entry.line == 0 || entry.line > max_valid {
+                warn!(line = entry.line, max = lines.len(), "invalid line");
+                had_error = true;
+            }
+        }
+        file_lines.insert(file.clone(), lines);
+    }
+
+    if had_error {
+        bail!("batch validation failed; no files were modified");
+    }
+
+    // Phase 2: apply. Highest line first per file so earlier inserts don't
+    // shift the indices of later ones.
+    let mut record = load_record(anchors_path)?;
+    let mut dropped = 0u32;
+
+    for (file, mut entries) in by_file {
+        let span = info_span!("batch_apply", path = %file.display());
+        let _enter = span.enter();
+
+        let path_str = file.display().to_string();
+        let mut lines = file_lines.remove(&file).expect("validated in phase 1");
+
+        entries.sort_by_key(|e| std::cmp::Reverse(e.line));
+        for entry in entries {
+            let key = generate_key();
+            let comment = build_comment(&file, &key, &entry.desc);
+            lines.insert(entry.line - 1, comment);
+
+            // Fingerprint the marker comment itself, post-insertion, so it
+            // matches the line `entry.line` actually points at on resync.
+            let window = context_window(&lines, entry.line - 1, CONTEXT_RADIUS);
+
+            for anchor in record
+                .anchors
+                .iter_mut()
+                .filter(|e| e.path == path_str && e.status != AnchorStatus::Orphaned && e.line >= entry.line)
+            {
+                anchor.line += 1;
+            }
+
+            record.anchors.push(AnchorEntry {
+                key: key.clone(),
+                path: path_str.clone(),
+                line: entry.line,
+                kind: entry.kind.map(AnchorKind::from).unwrap_or_default(),
+                description: entry.desc,
+                status: AnchorStatus::Active,
+                created: current_timestamp(),
+                tags: entry.tags,
+                anchor_hash: window.anchor_hash,
+                context_before: window.before,
+                context_after: window.after,
+                window_hash: window.window_hash,
+            });
+            dropped += 1;
+            info!(key = %key, line = entry.line, "anchor dropped");
+        }
+
+        atomic_write(&file, &lines.concat())?;
+    }
+
+    record.touch_generated();
+    write_record(anchors_path, &record)?;
 
-    println!(
-        "Anchor {} dropped at {}:{}",
-        key,
-        file_path.display(),
-        line_num
-    );
+    info!(count = dropped, "batch complete");
+    Ok(())
+}
+
+fn parse_batch_spec(spec_path: &Path, content: &str) -> Result<BatchSpec> {
+    let is_toml = spec_path.extension().and_then(|e| e.to_str()) == Some("toml");
+    if is_toml {
+        toml::from_str(content)
+            .with_context(|| format!("Failed to parse batch spec {}", spec_path.display()))
+    } else {
+        serde_json::from_str(content)
+            .with_context(|| format!("Failed to parse batch spec {}", spec_path.display()))
+    }
+}
+
+/// Filters anchors by tag/status/path-prefix/creation-date range and renders
+/// the result as a pretty table, JSON, or a Markdown report grouped by file.
+fn cmd_export(
+    anchors_path: &Path,
+    tag: Option<String>,
+    status: Option<String>,
+    path_prefix: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    format: ExportFormat,
+) -> Result<()> {
+    let record = load_record(anchors_path)?;
+
+    let status = status.map(|s| s.parse::<AnchorStatus>()).transpose()?;
+    let since = since.map(|s| parse_date_bound(&s)).transpose()?;
+    let until = until.map(|s| parse_date_bound(&s)).transpose()?;
+
+    let matches: Vec<&AnchorEntry> = record
+        .anchors
+        .iter()
+        .filter(|e| tag.as_deref().is_none_or(|t| e.tags.iter().any(|et| et == t)))
+        .filter(|e| status.is_none_or(|s| e.status == s))
+        .filter(|e| path_prefix.as_deref().is_none_or(|p| e.path.starts_with(p)))
+        // An entry whose `created` timestamp fails to parse has no date to
+        // compare against, so it's excluded by any active since/until bound
+        // rather than passing it by default.
+        .filter(|e| since.is_none_or(|d| entry_date(e).is_some_and(|ed| ed >= d)))
+        .filter(|e| until.is_none_or(|d| entry_date(e).is_some_and(|ed| ed <= d)))
+        .collect();
+
+    match format {
+        ExportFormat::Table => render_table(&matches),
+        ExportFormat::Json => render_json(&matches)?,
+        ExportFormat::Markdown => render_markdown(&matches),
+    }
+
+    Ok(())
+}
+
+fn parse_date_bound(s: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .with_context(|| format!("invalid date \"{s}\", expected YYYY-MM-DD"))
+}
+
+fn entry_date(entry: &AnchorEntry) -> Option<NaiveDate> {
+    NaiveDateTime::parse_from_str(&entry.created, "%Y-%m-%dT%H:%M:%SZ")
+        .ok()
+        .map(|dt| dt.date())
+}
+
+fn render_table(entries: &[&AnchorEntry]) {
+    if entries.is_empty() {
+        println!("No anchors match.");
+        return;
+    }
+
+    for entry in entries {
+        let tags = if entry.tags.is_empty() {
+            String::new()
+        } else {
+            format!("  #{}", entry.tags.join(" #"))
+        };
+        println!(
+            "{}  {}:{:<6} [{}/{}]  {}{}",
+            entry.key, entry.path, entry.line, entry.kind, entry.status, entry.description, tags
+        );
+    }
+}
 
+fn render_json(entries: &[&AnchorEntry]) -> Result<()> {
+    let json = serde_json::to_string_pretty(entries).context("Failed to serialize export")?;
+    println!("{json}");
     Ok(())
 }
 
+fn render_markdown(entries: &[&AnchorEntry]) {
+    let mut by_path: std::collections::BTreeMap<&str, Vec<&AnchorEntry>> =
+        std::collections::BTreeMap::new();
+    for entry in entries {
+        by_path.entry(entry.path.as_str()).or_default().push(entry);
+    }
+
+    for (path, group) in by_path {
+        println!("## {path}\n");
+        for entry in group {
+            println!("### [{path}:{}]({path}#L{})", entry.line, entry.line);
+            println!(
+                "\n{} _(kind: {}, status: {})_\n",
+                entry.description, entry.kind, entry.status
+            );
+        }
+    }
+}
+
+/// Whether `path` (as stored on an `AnchorEntry`) falls under `root`, using
+/// the same join convention `cmd_reindex` uses to build `stored_path`. A scan
+/// rooted anywhere but `.` only covers a subtree, so entries outside it must
+/// not be touched by reconciliation — they were simply never looked at.
+fn path_within_root(path: &str, root: &Path) -> bool {
+    if root == Path::new(".") {
+        return true;
+    }
+    let root_str = root.display().to_string();
+    path == root_str || path.starts_with(&format!("{root_str}/"))
+}
+
+/// Recursively lists files under `root`, returned as paths relative to it,
+/// skipping `IGNORED_DIRS`.
+fn collect_source_files(root: &Path) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    walk_dir(root, Path::new(""), &mut out);
+    out
+}
+
+fn walk_dir(dir: &Path, rel: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        if let Some(name) = file_name.to_str()
+            && IGNORED_DIRS.contains(&name)
+        {
+            continue;
+        }
+        let rel_path = rel.join(&file_name);
+        let full_path = entry.path();
+        if full_path.is_dir() {
+            walk_dir(&full_path, &rel_path, out);
+        } else {
+            out.push(rel_path);
+        }
+    }
+}
+
+/// Parses a `CLAUDE_ANCHOR[key=...] description` marker out of a source line.
+fn parse_marker(line: &str) -> Option<(String, String)> {
+    let marker_start = line.find(ANCHOR_MARKER)?;
+    let after = &line[marker_start + ANCHOR_MARKER.len()..];
+    let end = after.find(']')?;
+    let key = after[..end].to_string();
+    let desc = after[end + 1..]
+        .trim()
+        .trim_end_matches("-->")
+        .trim()
+        .to_string();
+    Some((key, desc))
+}
+
+fn find_entry<'a>(record: &'a AnchorsRecord, key: &str) -> Option<&'a AnchorEntry> {
+    record.anchors.iter().find(|e| e.key == key)
+}
+
+fn find_entry_mut<'a>(record: &'a mut AnchorsRecord, key: &str) -> Option<&'a mut AnchorEntry> {
+    record.anchors.iter_mut().find(|e| e.key == key)
+}
+
+/// Matches `text` against a `*`-wildcard glob pattern (no other special chars).
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let mut cursor = 0;
+    for (idx, part) in parts.iter().enumerate() {
+        if part.is_empty() {
+            continue;
+        }
+        if idx == 0 {
+            if !text[cursor..].starts_with(part) {
+                return false;
+            }
+            cursor += part.len();
+        } else if idx == parts.len() - 1 {
+            return text[cursor..].ends_with(part);
+        } else if let Some(found) = text[cursor..].find(part) {
+            cursor += found + part.len();
+        } else {
+            return false;
+        }
+    }
+    true
+}
+
+/// Finds and deletes the inline `CLAUDE_ANCHOR[key=...]` comment line for
+/// `key` in `path`, if present. Returns the 1-based line it was removed
+/// from, so the caller can shift other anchors in the same file up.
+fn remove_anchor_comment(path: &Path, key: &str) -> Result<Option<usize>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let marker = format!("CLAUDE_ANCHOR[key={key}]");
+    let mut lines = read_file_lines(path)?;
+    let Some(removed_at) = lines.iter().position(|l| l.contains(&marker)) else {
+        return Ok(None);
+    };
+    lines.remove(removed_at);
+    atomic_write(path, &lines.concat())?;
+
+    Ok(Some(removed_at + 1))
+}
+
+/// Writes `contents` to `path` by first writing a temp sibling file and then
+/// renaming it into place, so a crash mid-write can't truncate `path`.
+fn atomic_write(path: &Path, contents: &str) -> std::result::Result<(), AnchorError> {
+    let tmp_path = sibling_tmp_path(path);
+    fs::write(&tmp_path, contents).map_err(|_| AnchorError::WriteFailed(path.to_path_buf()))?;
+    fs::rename(&tmp_path, path).map_err(|_| AnchorError::WriteFailed(path.to_path_buf()))?;
+    Ok(())
+}
+
+fn sibling_tmp_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let suffix = &Uuid::new_v4().to_string()[..8];
+    path.with_file_name(format!(".{file_name}.tmp-{suffix}"))
+}
+
+/// A content-hash fingerprint of the lines surrounding an anchored position.
+struct ContextWindow {
+    anchor_hash: String,
+    before: Vec<String>,
+    after: Vec<String>,
+    /// Hash of the whole window concatenated together, so a resync can
+    /// cheaply tell "nothing in this neighborhood changed" without scoring
+    /// every candidate line in the file.
+    window_hash: String,
+}
+
+/// Builds the fingerprint for the line at `idx` (0-based) in `lines`, truncating
+/// the window at the start/end of the file.
+fn context_window(lines: &[String], idx: usize, radius: usize) -> ContextWindow {
+    if idx >= lines.len() {
+        // The anchor points one past the end of the file (appended content);
+        // there is no center line to hash, only whatever precedes it.
+        let start = lines.len().saturating_sub(radius);
+        let before = &lines[start..];
+        return ContextWindow {
+            anchor_hash: String::new(),
+            before: before.iter().map(|l| hash_line(l)).collect(),
+            after: Vec::new(),
+            window_hash: hash_window(before),
+        };
+    }
+
+    let before_start = idx.saturating_sub(radius);
+    let after_end = (idx + 1 + radius).min(lines.len());
+    let window = &lines[before_start..after_end];
+
+    ContextWindow {
+        anchor_hash: hash_line(&lines[idx]),
+        before: lines[before_start..idx].iter().map(|l| hash_line(l)).collect(),
+        after: lines[idx + 1..after_end].iter().map(|l| hash_line(l)).collect(),
+        window_hash: hash_window(window),
+    }
+}
+
+fn hash_line(line: &str) -> String {
+    let hash = fnv1a_fold(FNV_OFFSET_BASIS, line.trim_end_matches('\n').as_bytes());
+    format!("{hash:016x}")
+}
+
+/// Hashes a whole window of lines as one unit, as opposed to `hash_line`'s
+/// per-line fingerprint. Each line is followed by a boundary byte so e.g.
+/// `["ab", "c"]` and `["a", "bc"]` don't collide.
+fn hash_window(lines: &[String]) -> String {
+    let mut hash = FNV_OFFSET_BASIS;
+    for line in lines {
+        hash = fnv1a_fold(hash, line.trim_end_matches('\n').as_bytes());
+        hash = fnv1a_fold(hash, &[0xff]);
+    }
+    format!("{hash:016x}")
+}
+
+/// Folds `bytes` into a running FNV-1a hash.
+fn fnv1a_fold(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Re-reads `lines` (the current contents of the file an anchor is recorded
+/// against) and, for every tracked anchor whose stored line no longer matches
+/// its content hash, scans for the best-scoring candidate position. Updates
+/// `line`/context hashes in place on a confident move, or flags the anchor
+/// `"orphaned"`/`"ambiguous"` when it can't be resolved.
+fn relocate_anchors_for_path(record: &mut AnchorsRecord, path: &str, lines: &[String]) {
+    for entry in record.anchors.iter_mut().filter(|e| e.path == path) {
+        relocate_one(entry, lines);
+    }
+}
+
+fn relocate_one(entry: &mut AnchorEntry, lines: &[String]) {
+    // A resolved anchor is done being tracked for drift: if it was stripped,
+    // its marker is gone on purpose; if it was left in place, we still don't
+    // want a nearby edit flipping it back to "moved" and resurfacing it.
+    if entry.status == AnchorStatus::Resolved {
+        return;
+    }
+
+    // Entry already matches the window it claims; nothing to do. Checking
+    // the whole window, not just the center line, catches edits to the
+    // context around an anchor even when the center line itself still
+    // happens to match by coincidence.
+    if entry.line >= 1 && entry.line <= lines.len() {
+        let window = context_window(lines, entry.line - 1, CONTEXT_RADIUS);
+        if window.window_hash == entry.window_hash {
+            return;
+        }
+    }
+
+    let mut scored: Vec<(usize, u32)> = (0..lines.len())
+        .map(|idx| (idx, score_candidate(entry, lines, idx)))
+        .filter(|(_, score)| *score > 0)
+        .collect();
+    scored.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+
+    let best = scored.first().copied();
+    let runner_up_score = scored.get(1).map(|(_, s)| *s).unwrap_or(0);
+
+    match best {
+        Some((idx, score)) if score >= RELOCATE_THRESHOLD && score - runner_up_score >= RELOCATE_MARGIN => {
+            let window = context_window(lines, idx, CONTEXT_RADIUS);
+            entry.line = idx + 1;
+            entry.anchor_hash = window.anchor_hash;
+            entry.context_before = window.before;
+            entry.context_after = window.after;
+            entry.window_hash = window.window_hash;
+            entry.status = AnchorStatus::Moved;
+        }
+        Some(_) => {
+            entry.status = AnchorStatus::Ambiguous;
+        }
+        None => {
+            entry.status = AnchorStatus::Orphaned;
+        }
+    }
+}
+
+/// Scores how well the window around `idx` in `lines` matches `entry`'s stored
+/// fingerprint: the center line counts for `CENTER_WEIGHT`, each matching
+/// context line counts for 1.
+fn score_candidate(entry: &AnchorEntry, lines: &[String], idx: usize) -> u32 {
+    let mut score = 0u32;
+
+    if !entry.anchor_hash.is_empty() && hash_line(&lines[idx]) == entry.anchor_hash {
+        score += CENTER_WEIGHT;
+    }
+
+    let before_start = idx.saturating_sub(entry.context_before.len());
+    for (offset, expected) in lines[before_start..idx].iter().rev().zip(entry.context_before.iter().rev()) {
+        if hash_line(offset) == *expected {
+            score += 1;
+        }
+    }
+
+    let after_end = (idx + 1 + entry.context_after.len()).min(lines.len());
+    for (actual, expected) in lines[idx + 1..after_end].iter().zip(entry.context_after.iter()) {
+        if hash_line(actual) == *expected {
+            score += 1;
+        }
+    }
+
+    score
+}
+
 fn read_file_lines(path: &Path) -> Result<Vec<String>> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read file {}", path.display()))?;
@@ -179,21 +1272,15 @@ fn load_record(path: &Path) -> Result<AnchorsRecord> {
     let file = File::open(path)
         .with_context(|| format!("Failed to open anchors file {}", path.display()))?;
     let reader = BufReader::new(file);
-    match serde_json::from_reader(reader) {
-        Ok(record) => Ok(record),
-        Err(_) => {
-            println!("Invalid anchors.json; reinitializing.");
-            Ok(AnchorsRecord::new())
-        }
-    }
+    serde_json::from_reader(reader).map_err(|_| AnchorError::CorruptStore(path.to_path_buf()).into())
 }
 
 fn write_record(path: &Path, record: &AnchorsRecord) -> Result<()> {
-    let file = File::create(path)
-        .with_context(|| format!("Failed to write anchors file {}", path.display()))?;
-    serde_json::to_writer_pretty(file, record).context("Failed to serialize anchors record")
+    let contents = serde_json::to_string_pretty(record).context("Failed to serialize anchors record")?;
+    atomic_write(path, &contents)?;
+    Ok(())
 }
 
 fn current_timestamp() -> String {
     Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
-}
\ No newline at end of file
+}